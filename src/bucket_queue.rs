@@ -0,0 +1,623 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::PriorityQueue;
+
+/// Controls which end of a priority bucket `pop`/`peek` draw from.
+///
+/// Buckets are ordered lists of length-prefixed elements sharing a priority;
+/// `Policy` just picks which end of that list counts as "the top" of the
+/// queue for a given instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// `pop`/`peek` drain the first element inserted for a priority (FIFO).
+    Fifo,
+    /// `pop`/`peek` drain the most recently inserted element for a priority (LIFO).
+    Lifo,
+}
+
+pub struct PriorityQueueImpl {
+    buckets: BTreeMap<u64, Vec<u8>>,
+    policy: Policy,
+    /// For each element currently live (inserted and not yet superseded by a later
+    /// `change_priority` call), the set of encoded copies' sequence numbers that still count as
+    /// authoritative. See the impl docs for why a set of sequence numbers, not just a priority,
+    /// is needed here.
+    live_seqs: HashMap<Vec<u8>, HashSet<u64>>,
+    /// Increasing counter (reset to 0 only by `clear`); every encoded copy gets the next value so `pop` can
+    /// tell two copies of the same bytes apart even if they were inserted at the same priority.
+    next_seq: u64,
+}
+
+/// Priority Queue
+///
+/// Buckets elements by priority in a `BTreeMap<u64, Vec<u8>>` so the highest priority is always
+/// the last key in iteration order, making `peek`/`pop` O(log n) in the number of distinct
+/// priorities instead of a linear scan. Each bucket is a list of length-prefixed elements;
+/// `Policy` chooses whether `pop`/`peek` drain the front (FIFO, the default) or the back (LIFO)
+/// of that list.
+///
+/// A side map tracks, per element, the set of encoded copies that are still "live" (not yet
+/// superseded by a later `change_priority` call) by sequence number. Every encoded copy is
+/// tagged with a sequence number distinct from every other copy currently in the queue, so
+/// `change_priority` can implement lazy deletion by inserting a fresh copy at the new priority
+/// and dropping the old copy's sequence number from the live set, without needing to locate and
+/// remove the old copy's bytes in place. `pop`/`pop_back` discard any popped copy whose sequence
+/// number isn't in the live set before returning. Comparing priorities alone isn't enough here:
+/// if `change_priority` is called more than once for the same element and a later priority
+/// happens to repeat an earlier one, two encoded copies can end up sharing both the element's
+/// bytes and its current authoritative priority, even though only one of them is actually live.
+/// This means elements must be unique while using `change_priority`, and the queue's memory
+/// grows with the number of stale updates until they are popped.
+///
+/// Make sure to only initialize with PriorityQueueImpl::new() or PriorityQueueImpl::with_policy()
+/// Manual initialization will cause undesireable behavior due to encoded values and will panic
+impl PriorityQueue<Vec<u8>> for PriorityQueueImpl {
+    /// Exact same semantics as `[BTreeMap::new()]`
+    ///
+    /// PriorityQueueImpl::new() creates an empty tree map with no allocation
+    /// until it is inserted into. Defaults to `Policy::Fifo`.
+    fn new() -> Self {
+        Self {
+            buckets: BTreeMap::new(),
+            policy: Policy::Fifo,
+            live_seqs: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Exact same semantics as `[BTreeMap::is_empty()]`
+    /// infaillible and will not panic
+    fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Gets the value with the highest priority number, drawn from whichever
+    /// end of the bucket this queue's `Policy` selects.
+    /// Will return None in the case of an empty queue
+    ///
+    /// Note: unlike `pop`, `peek` does not mutate the queue, so it cannot discard a stale
+    /// duplicate left behind by `change_priority`. It's possible (though rare in practice) for
+    /// `peek` to return an element whose priority was since superseded.
+    ///
+    /// #[Panic]
+    ///
+    /// Will panic if elements were manually initialized and
+    /// dont follow insert encoding convention
+    fn peek(&self) -> Option<Vec<u8>> {
+        let (_, bucket) = self.buckets.iter().next_back()?;
+        let span = match self.policy {
+            Policy::Fifo => front_element_span(bucket),
+            Policy::Lifo => back_element_span(bucket),
+        };
+        Some(bucket[span.data].to_vec())
+    }
+
+    /// Inserts an element into the bucket for `priority`, creating the bucket if needed, and
+    /// marks the copy's fresh sequence number as authoritative for `change_priority`
+    ///
+    /// the element is encoded by prefixing it with its length as a LEB128 varint (7 bits per
+    /// byte, high bit set while more bytes follow), so vec![0, 0] encodes to vec![2, 0, 0],
+    /// followed by an 8-byte big-endian sequence number unique to this copy. This allows buckets
+    /// to hold multiple elements of arbitrary size sharing a priority, ordered by insertion, and
+    /// lets `pop` tell copies of identical bytes apart.
+    fn insert(&mut self, element: Vec<u8>, priority: u64) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.live_seqs.entry(element.clone()).or_default().insert(seq);
+
+        let mut encoded = Vec::with_capacity(element.len() + 5 + SEQ_LEN);
+        encode_varint(element.len(), &mut encoded);
+        encoded.extend_from_slice(&element);
+        encoded.extend_from_slice(&seq.to_be_bytes());
+
+        // will insert priority and bucket if priority is not in use.
+        // if priority is in use the element is pushed onto the end of the existing bucket
+        self.buckets.entry(priority).or_default().append(&mut encoded);
+    }
+
+    /// Inserts a fresh bucket entry for `element` at `new_priority` without removing the stale
+    /// entry at its old priority; see the lazy-deletion note on the impl docs. Elements must be
+    /// unique while using this method, since it retires every live copy of `element` and leaves
+    /// a single live copy at `new_priority`.
+    fn change_priority(&mut self, element: &Vec<u8>, new_priority: u64) {
+        if let Some(live) = self.live_seqs.get_mut(element) {
+            // the existing copy (if any) is no longer authoritative once it moves
+            live.clear();
+        }
+        self.insert(element.clone(), new_priority);
+    }
+
+    /// Pops the highest priority element off the queue, drawn from whichever
+    /// end of the bucket this queue's `Policy` selects.
+    ///
+    /// Discards and keeps looking if the popped copy is a stale duplicate left behind by
+    /// `change_priority` (its sequence number is no longer in the live set).
+    ///
+    /// #[Panic]
+    ///
+    /// will panic if data is inserted into the tree map manually and does not
+    /// follow encoding convention of insert()
+    fn pop(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let top_key = *self.buckets.keys().next_back()?;
+            let bucket = self.buckets.get_mut(&top_key).expect("top_key was just read from keys()");
+            let span = match self.policy {
+                Policy::Fifo => front_element_span(bucket),
+                Policy::Lifo => back_element_span(bucket),
+            };
+            let element = bucket[span.data.clone()].to_vec();
+            let seq = read_seq(bucket, &span);
+            bucket.drain(span.prefix.start..span.seq.end);
+            if bucket.is_empty() {
+                // remove priority and bucket if bucket is empty
+                self.buckets.remove(&top_key);
+            }
+            if self.consume_if_live(&element, seq) {
+                return Some(element);
+            }
+            // stale duplicate from an earlier change_priority call; discard and keep looking
+        }
+    }
+}
+
+impl PriorityQueueImpl {
+    /// Creates a new priority queue whose `pop`/`peek` drain `policy`'s end
+    /// of each priority bucket instead of the default FIFO order.
+    pub fn with_policy(policy: Policy) -> Self {
+        Self {
+            buckets: BTreeMap::new(),
+            policy,
+            live_seqs: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Peeks the back of the highest-priority bucket, regardless of this
+    /// queue's `Policy`. Will return None if the queue is empty.
+    ///
+    /// #[Panic]
+    ///
+    /// Will panic if elements were manually initialized and
+    /// dont follow insert encoding convention
+    pub fn peek_back(&self) -> Option<Vec<u8>> {
+        let (_, bucket) = self.buckets.iter().next_back()?;
+        Some(bucket[back_element_span(bucket).data].to_vec())
+    }
+
+    /// Pops the back of the highest-priority bucket, regardless of this
+    /// queue's `Policy`. Will return None if the queue is empty.
+    ///
+    /// #[Panic]
+    ///
+    /// will panic if data is inserted into the tree map manually and does not
+    /// follow encoding convention of insert()
+    pub fn pop_back(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let top_key = *self.buckets.keys().next_back()?;
+            let bucket = self.buckets.get_mut(&top_key).expect("top_key was just read from keys()");
+            let span = back_element_span(bucket);
+            let element = bucket[span.data.clone()].to_vec();
+            let seq = read_seq(bucket, &span);
+            bucket.drain(span.prefix.start..span.seq.end);
+            if bucket.is_empty() {
+                self.buckets.remove(&top_key);
+            }
+            if self.consume_if_live(&element, seq) {
+                return Some(element);
+            }
+            // stale duplicate from an earlier change_priority call; discard and keep looking
+        }
+    }
+
+    /// Removes `seq` from `element`'s live set if present, returning whether it was there.
+    /// A copy whose sequence number isn't in the live set is a stale duplicate left behind by
+    /// `change_priority` and should be discarded rather than returned.
+    fn consume_if_live(&mut self, element: &[u8], seq: u64) -> bool {
+        let Some(live) = self.live_seqs.get_mut(element) else {
+            return false;
+        };
+        let was_live = live.remove(&seq);
+        if live.is_empty() {
+            self.live_seqs.remove(element);
+        }
+        was_live
+    }
+
+    /// Consumes the queue and returns all elements in descending-priority order, with ties
+    /// broken according to this queue's `Policy` (FIFO by default).
+    pub fn into_sorted_vec(mut self) -> Vec<Vec<u8>> {
+        let mut sorted = Vec::with_capacity(self.len());
+        while let Some(element) = self.pop() {
+            sorted.push(element);
+        }
+        sorted
+    }
+
+    /// Returns a draining iterator that pops elements in priority order, leaving the queue
+    /// empty once exhausted.
+    pub fn drain(&mut self) -> Drain<'_> {
+        Drain(self)
+    }
+
+    /// Returns a borrowing iterator over the queue's live elements in arbitrary order, for
+    /// inspection without modifying the queue. Stale duplicates left behind by `change_priority`
+    /// are skipped, not yielded.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            live_seqs: &self.live_seqs,
+            buckets: self.buckets.values(),
+            current: None,
+        }
+    }
+
+    /// Returns the number of live elements currently in the queue, not counting stale duplicates
+    /// left behind by `change_priority`.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Exact same semantics as `[PriorityQueue::is_empty]`; exposed as an inherent method too
+    /// so it pairs with `len`. Stale duplicates left behind by `change_priority` still count
+    /// here, so `is_empty` can return false while `len` returns 0.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Removes all elements from the queue.
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.live_seqs.clear();
+        self.next_seq = 0;
+    }
+}
+
+/// Draining iterator created by `[PriorityQueueImpl::drain]`; pops elements in priority order.
+pub struct Drain<'a>(&'a mut PriorityQueueImpl);
+
+impl Iterator for Drain<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        self.0.pop()
+    }
+}
+
+/// Borrowing iterator created by `[PriorityQueueImpl::iter]`; yields live elements in arbitrary
+/// order, skipping stale duplicates left behind by `change_priority`.
+pub struct Iter<'a> {
+    live_seqs: &'a HashMap<Vec<u8>, HashSet<u64>>,
+    buckets: std::collections::btree_map::Values<'a, u64, Vec<u8>>,
+    current: Option<(&'a [u8], usize)>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Some((bucket, offset)) = &mut self.current {
+                if *offset < bucket.len() {
+                    let span = front_element_span(&bucket[*offset..]);
+                    let data = bucket[*offset + span.data.start..*offset + span.data.end].to_vec();
+                    let seq = read_seq(&bucket[*offset..], &span);
+                    *offset += span.seq.end;
+                    if self.live_seqs.get(&data).is_some_and(|live| live.contains(&seq)) {
+                        return Some(data);
+                    }
+                    // stale duplicate from an earlier change_priority call; skip it
+                    continue;
+                }
+            }
+            self.current = Some((self.buckets.next()?.as_slice(), 0));
+        }
+    }
+}
+
+/// Number of bytes used to encode each entry's sequence number (see `PriorityQueueImpl::insert`).
+const SEQ_LEN: usize = 8;
+
+/// The position of a decoded element within an encoded bucket: `prefix` is the varint length
+/// header, `data` is the element's own bytes, and `seq` is its trailing sequence number.
+struct ElementSpan {
+    prefix: Range<usize>,
+    data: Range<usize>,
+    seq: Range<usize>,
+}
+
+/// Returns the span of the first (FIFO) element within an encoded bucket.
+fn front_element_span(bucket: &[u8]) -> ElementSpan {
+    let (element_len, prefix_len) = decode_varint(bucket);
+    let data = prefix_len..prefix_len + element_len;
+    ElementSpan {
+        prefix: 0..prefix_len,
+        seq: data.end..data.end + SEQ_LEN,
+        data,
+    }
+}
+
+/// Returns the sequence number encoded in `span` of `bucket`.
+fn read_seq(bucket: &[u8], span: &ElementSpan) -> u64 {
+    u64::from_be_bytes(bucket[span.seq.clone()].try_into().expect("seq field is always 8 bytes"))
+}
+
+/// Returns the span of the last (LIFO) element within an encoded bucket.
+///
+/// Elements are only length-prefixed from the front, so finding the last
+/// one requires walking the whole bucket.
+fn back_element_span(bucket: &[u8]) -> ElementSpan {
+    let mut offset = 0;
+    loop {
+        let span = front_element_span(&bucket[offset..]);
+        let absolute = ElementSpan {
+            prefix: offset + span.prefix.start..offset + span.prefix.end,
+            data: offset + span.data.start..offset + span.data.end,
+            seq: offset + span.seq.start..offset + span.seq.end,
+        };
+        if absolute.seq.end >= bucket.len() {
+            return absolute;
+        }
+        offset = absolute.seq.end;
+    }
+}
+
+/// Encodes `value` as a LEB128 varint: 7 bits per byte, with the high bit set on every byte
+/// except the last to signal "more bytes follow".
+fn encode_varint(mut value: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+/// Decodes a LEB128 varint from the front of `bytes`, returning the decoded value and the
+/// number of bytes the prefix itself occupied.
+///
+/// #[Panic]
+///
+/// Will panic if `bytes` ends before a continuation byte (high bit set) is followed by a
+/// terminating byte (high bit clear).
+fn decode_varint(bytes: &[u8]) -> (usize, usize) {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+        shift += 7;
+    }
+    panic!("truncated varint length prefix");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let mut queue = PriorityQueueImpl::new();
+        assert!(queue.is_empty());
+
+        queue.insert(vec![0], 5);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.peek(), Some(vec![0]));
+
+        queue.insert(vec![1], 10);
+        queue.insert(vec![2], 3);
+        queue.insert(vec![3], 4);
+        queue.insert(vec![4], 6);
+
+        assert_eq!(queue.pop(), Some(vec![1]));
+        assert_eq!(queue.pop(), Some(vec![4]));
+        assert_eq!(queue.pop(), Some(vec![0]));
+        assert_eq!(queue.pop(), Some(vec![3]));
+        assert_eq!(queue.pop(), Some(vec![2]));
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+    }
+
+    #[test]
+    fn large_number() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![10], 10000);
+        queue.insert(vec![5], 25000);
+        queue.insert(vec![0], 0);
+        assert_eq!(queue.peek(), Some(vec![5]));
+    }
+
+    #[test]
+    fn key_is_zero() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![10, 3], 0);
+        assert_eq!(queue.peek(), Some(vec![10, 3]));
+        assert_eq!(queue.pop(), Some(vec![10, 3]));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn key_with_multiple_elements() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![10; 5], 10);
+        queue.insert(vec![5], 10);
+        queue.insert(vec![100; 20], 10);
+        assert_eq!(queue.pop(), Some(vec![10; 5]));
+        assert_eq!(queue.pop(), Some(vec![5]));
+        assert_eq!(queue.pop(), Some(vec![100; 20]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn element_empty_vec() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(Vec::new(), 10);
+        queue.insert(Vec::new(), 10);
+        assert_eq!(queue.peek(), Some(vec![]));
+        assert_eq!(queue.pop(), Some(vec![]));
+        assert_eq!(queue.peek(), Some(vec![]));
+        assert_eq!(queue.pop(), Some(vec![]));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn element_larger_than_255_bytes_does_not_panic() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![7; 1000], 10);
+        queue.insert(vec![9], 20);
+
+        assert_eq!(queue.pop(), Some(vec![9]));
+        assert_eq!(queue.pop(), Some(vec![7; 1000]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn manually_init_elements() {
+        let mut btree = BTreeMap::new();
+        btree.insert(0u64, vec![10, 0, 0]);
+        let queue = PriorityQueueImpl {
+            buckets: btree,
+            policy: Policy::Fifo,
+            live_seqs: HashMap::new(),
+            next_seq: 0,
+        };
+        assert_eq! {queue.peek(), None};
+    }
+
+    #[test]
+    fn change_priority_discards_stale_duplicate_on_pop() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![9], 1);
+        queue.insert(vec![1], 5);
+
+        queue.change_priority(&vec![9], 10);
+
+        assert_eq!(queue.pop(), Some(vec![9]));
+        assert_eq!(queue.pop(), Some(vec![1]));
+        // draining the stale bucket left at the old priority (1) surfaces no live element
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn repeated_change_priority_does_not_duplicate_the_element() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![9], 1);
+
+        // raise the priority, then lower it back onto the bucket that still holds the first
+        // (now stale) copy of [9]; a sequence-based live check is needed to avoid matching both.
+        queue.change_priority(&vec![9], 10);
+        queue.change_priority(&vec![9], 1);
+
+        assert_eq!(queue.pop(), Some(vec![9]));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn lifo_policy_pops_most_recent_within_a_priority() {
+        let mut queue = PriorityQueueImpl::with_policy(Policy::Lifo);
+        queue.insert(vec![1], 5);
+        queue.insert(vec![2], 5);
+        queue.insert(vec![3], 5);
+
+        assert_eq!(queue.pop(), Some(vec![3]));
+        assert_eq!(queue.pop(), Some(vec![2]));
+        assert_eq!(queue.pop(), Some(vec![1]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pop_back_ignores_policy() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![1], 5);
+        queue.insert(vec![2], 5);
+        queue.insert(vec![3], 5);
+
+        assert_eq!(queue.peek_back(), Some(vec![3]));
+        assert_eq!(queue.pop_back(), Some(vec![3]));
+        assert_eq!(queue.pop(), Some(vec![1]));
+        assert_eq!(queue.pop_back(), Some(vec![2]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn into_sorted_vec_returns_descending_priority_order() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![1], 10);
+        queue.insert(vec![2], 3);
+        queue.insert(vec![3], 30);
+
+        assert_eq!(
+            queue.into_sorted_vec(),
+            vec![vec![3], vec![1], vec![2]]
+        );
+    }
+
+    #[test]
+    fn drain_pops_in_priority_order_and_empties_the_queue() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![1], 10);
+        queue.insert(vec![2], 3);
+
+        let drained: Vec<Vec<u8>> = queue.drain().collect();
+        assert_eq!(drained, vec![vec![1], vec![2]]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn iter_does_not_mutate_the_queue() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![1], 10);
+        queue.insert(vec![2], 3);
+
+        let mut seen: Vec<Vec<u8>> = queue.iter().collect();
+        seen.sort();
+        assert_eq!(seen, vec![vec![1], vec![2]]);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn len_and_clear() {
+        let mut queue = PriorityQueueImpl::new();
+        assert_eq!(queue.len(), 0);
+
+        queue.insert(vec![1], 10);
+        queue.insert(vec![2], 10);
+        queue.insert(vec![3], 3);
+        assert_eq!(queue.len(), 3);
+
+        queue.clear();
+        assert_eq!(queue.len(), 0);
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+    }
+
+    #[test]
+    fn len_and_iter_skip_stale_duplicates_left_by_change_priority() {
+        let mut queue = PriorityQueueImpl::new();
+        queue.insert(vec![9], 1);
+        queue.change_priority(&vec![9], 5);
+
+        // the stale copy at priority 1 is still physically in the queue until popped, but
+        // should not be counted or yielded as a live element
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![vec![9]]);
+
+        assert_eq!(queue.pop(), Some(vec![9]));
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.iter().next(), None);
+    }
+}
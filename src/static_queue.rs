@@ -0,0 +1,167 @@
+use crate::PriorityQueue;
+
+/// Returned by `[StaticPriorityQueue::try_insert]` when the queue has reached its
+/// compile-time capacity `N` and has no room for another element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFullError;
+
+/// A fixed-capacity priority queue backed by a caller-sized array instead of a heap-allocated
+/// collection, for use on `no_std` / embedded targets without an allocator.
+///
+/// Entries are kept densely packed in `entries[..len]` in insertion order; `peek`/`pop` scan
+/// that slice for the highest priority, so both are O(N) rather than the O(log n) the
+/// heap-backed `PriorityQueueImpl` gets from its `BTreeMap`. For the small, compile-time-bounded
+/// N this type is meant for, that's the right trade for avoiding an allocator entirely.
+pub struct StaticPriorityQueue<Element, const N: usize> {
+    entries: [Option<(Element, u64)>; N],
+    len: usize,
+}
+
+impl<Element: Copy + PartialEq, const N: usize> PriorityQueue<Element> for StaticPriorityQueue<Element, N> {
+    /// Creates an empty queue. `N` is fixed at compile time and no allocation ever happens.
+    fn new() -> Self {
+        Self {
+            entries: [None; N],
+            len: 0,
+        }
+    }
+
+    /// infaillible and will not panic
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the highest-priority element, ties broken in FIFO (insertion) order, without
+    /// modifying the queue. Returns None if the queue is empty.
+    fn peek(&self) -> Option<Element> {
+        let i = self.highest_priority_index()?;
+        Some(self.entries[i].expect("index came from highest_priority_index").0)
+    }
+
+    /// Inserts `element` with `priority`, growing `len` by one.
+    ///
+    /// #[Panic]
+    ///
+    /// Will panic if the queue is already at capacity `N`. Use `[Self::try_insert]` to handle
+    /// a full queue without panicking, which is normally what embedded callers want.
+    fn insert(&mut self, element: Element, priority: u64) {
+        self.try_insert(element, priority)
+            .expect("StaticPriorityQueue is at capacity")
+    }
+
+    /// Removes and returns the highest-priority element, ties broken in FIFO (insertion) order.
+    /// Returns None if the queue is empty.
+    fn pop(&mut self) -> Option<Element> {
+        let i = self.highest_priority_index()?;
+        let (element, _) = self.entries[i].take().expect("index came from highest_priority_index");
+        for j in i..self.len - 1 {
+            self.entries[j] = self.entries[j + 1].take();
+        }
+        self.len -= 1;
+        Some(element)
+    }
+
+    /// Updates the priority of the first entry equal to `element`, in place. Does nothing if
+    /// no entry matches.
+    fn change_priority(&mut self, element: &Element, new_priority: u64) {
+        if let Some((_, priority)) = self.entries[..self.len]
+            .iter_mut()
+            .flatten()
+            .find(|(entry, _)| entry == element)
+        {
+            *priority = new_priority;
+        }
+    }
+}
+
+impl<Element: Copy, const N: usize> StaticPriorityQueue<Element, N> {
+    /// Inserts `element` with `priority`, or reports `[QueueFullError]` instead of growing past
+    /// the compile-time capacity `N`.
+    pub fn try_insert(&mut self, element: Element, priority: u64) -> Result<(), QueueFullError> {
+        if self.len == N {
+            return Err(QueueFullError);
+        }
+        self.entries[self.len] = Some((element, priority));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the index of the entry with the highest priority, preferring the earliest
+    /// inserted entry among ties (FIFO).
+    fn highest_priority_index(&self) -> Option<usize> {
+        let mut best: Option<(usize, u64)> = None;
+        for (i, &(_, priority)) in self.entries[..self.len].iter().flatten().enumerate() {
+            if best.is_none_or(|(_, best_priority)| priority > best_priority) {
+                best = Some((i, priority));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let mut queue: StaticPriorityQueue<u8, 4> = StaticPriorityQueue::new();
+        assert!(queue.is_empty());
+
+        queue.insert(0, 5);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.peek(), Some(0));
+
+        queue.insert(1, 10);
+        queue.insert(2, 3);
+        queue.insert(3, 4);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(2));
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+    }
+
+    #[test]
+    fn fifo_tie_break() {
+        let mut queue: StaticPriorityQueue<u8, 3> = StaticPriorityQueue::new();
+        queue.insert(1, 5);
+        queue.insert(2, 5);
+        queue.insert(3, 5);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), Some(3));
+    }
+
+    #[test]
+    fn try_insert_reports_queue_full_instead_of_panicking() {
+        let mut queue: StaticPriorityQueue<u8, 2> = StaticPriorityQueue::new();
+        assert_eq!(queue.try_insert(1, 1), Ok(()));
+        assert_eq!(queue.try_insert(2, 2), Ok(()));
+        assert_eq!(queue.try_insert(3, 3), Err(QueueFullError));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_panics_when_full() {
+        let mut queue: StaticPriorityQueue<u8, 1> = StaticPriorityQueue::new();
+        queue.insert(1, 1);
+        queue.insert(2, 2);
+    }
+
+    #[test]
+    fn change_priority_reorders_without_lazy_duplicates() {
+        let mut queue: StaticPriorityQueue<u8, 3> = StaticPriorityQueue::new();
+        queue.insert(9, 1);
+        queue.insert(1, 5);
+
+        queue.change_priority(&9, 10);
+
+        assert_eq!(queue.pop(), Some(9));
+        assert_eq!(queue.pop(), Some(1));
+        assert!(queue.is_empty());
+    }
+}